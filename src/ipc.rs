@@ -0,0 +1,198 @@
+use std::io::{self, Result};
+use std::sync::Arc;
+
+use arrow::array::RecordBatch;
+use arrow::datatypes::Schema;
+use arrow::ipc::writer::StreamWriter;
+use bytes::Bytes;
+
+use crate::parquet::records::ElementBatches;
+
+/// Arrow IPC stream counterpart to `crate::parquet::writer::ParquetData`: a
+/// completed stream buffer for one element type, ready to hand to a `FileWriter`,
+/// paired with the row count written to it.
+#[derive(Debug)]
+pub enum ArrowIpcData {
+    Node(Bytes, usize),
+    Way(Bytes, usize),
+    Relation(Bytes, usize),
+}
+
+/// Mirrors `crate::parquet::writer::ParquetStreamWriter`, but emits an Arrow IPC
+/// stream (schema message, then record batch messages, then an end-of-stream
+/// marker) instead of a Parquet file. The same `.arrow` bytes produced here are
+/// also valid as Feather v2 if written with a file wrapper, but a stream needs no
+/// footer, which keeps rotation as simple as the Parquet memory writer's.
+pub trait ArrowIpcStreamWriter {
+    fn schema(&self) -> Arc<Schema>;
+    fn write(&mut self, record: &RecordBatch) -> Result<()>;
+    fn num_rows(&self) -> usize;
+    fn num_bytes(&self) -> usize;
+    fn should_flush(&self) -> bool;
+    /// Returns the flushed stream's bytes paired with the row count written to it.
+    fn flush(&mut self) -> Result<Option<(Bytes, usize)>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct ArrowIpcFileConfig {
+    max_rows_per_file: Option<usize>,
+    max_file_size_bytes: Option<usize>,
+}
+
+impl ArrowIpcFileConfig {
+    pub fn new() -> Self {
+        Self {
+            max_rows_per_file: None,
+            max_file_size_bytes: Some(128 * 1024 * 1024),
+        }
+    }
+
+    pub fn with_max_rows_per_file(mut self, max_rows_per_file: Option<usize>) -> Self {
+        self.max_rows_per_file = max_rows_per_file;
+        self
+    }
+
+    pub fn with_max_file_size_bytes(mut self, max_file_size_bytes: Option<usize>) -> Self {
+        self.max_file_size_bytes = max_file_size_bytes;
+        self
+    }
+}
+
+impl Default for ArrowIpcFileConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct ArrowIpcMemoryStreamWriter {
+    schema: Arc<Schema>,
+    config: ArrowIpcFileConfig,
+    writer: Option<StreamWriter<Vec<u8>>>,
+    num_rows: usize,
+    num_bytes: usize,
+}
+
+impl ArrowIpcMemoryStreamWriter {
+    pub fn new(schema: Arc<Schema>, config: Option<ArrowIpcFileConfig>) -> Self {
+        Self {
+            schema,
+            config: config.unwrap_or_default(),
+            writer: None,
+            num_rows: 0,
+            num_bytes: 0,
+        }
+    }
+}
+
+impl ArrowIpcStreamWriter for ArrowIpcMemoryStreamWriter {
+    fn schema(&self) -> Arc<Schema> {
+        self.schema.clone()
+    }
+
+    fn write(&mut self, record: &RecordBatch) -> Result<()> {
+        if self.writer.is_none() {
+            self.writer =
+                Some(StreamWriter::try_new(Vec::new(), &self.schema).map_err(io::Error::from)?);
+        }
+        let writer = self.writer.as_mut().unwrap();
+        writer.write(record).map_err(io::Error::from)?;
+        self.num_rows += record.num_rows();
+        // `StreamWriter` doesn't expose bytes written mid-stream, so approximate
+        // with the batch's in-memory size for the rotation threshold below.
+        self.num_bytes += record.get_array_memory_size();
+        Ok(())
+    }
+
+    fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    fn num_bytes(&self) -> usize {
+        self.num_bytes
+    }
+
+    fn should_flush(&self) -> bool {
+        if self.writer.is_none() {
+            return false;
+        }
+        if let Some(max_file_size) = self.config.max_file_size_bytes {
+            if self.num_bytes > max_file_size {
+                return true;
+            }
+        }
+        if let Some(max_rows_per_file) = self.config.max_rows_per_file {
+            if self.num_rows > max_rows_per_file {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn flush(&mut self) -> Result<Option<(Bytes, usize)>> {
+        if let Some(writer) = self.writer.take() {
+            let buf = writer.into_inner().map_err(io::Error::from)?;
+            let rows = self.num_rows;
+            self.num_rows = 0;
+            self.num_bytes = 0;
+
+            if buf.is_empty() {
+                return Ok(None);
+            }
+            return Ok(Some((Bytes::from(buf), rows)));
+        }
+        Ok(None)
+    }
+}
+
+pub struct OsmArrowIpcStreamWriter {
+    nodes: Box<dyn ArrowIpcStreamWriter>,
+    ways: Box<dyn ArrowIpcStreamWriter>,
+    relations: Box<dyn ArrowIpcStreamWriter>,
+}
+
+impl OsmArrowIpcStreamWriter {
+    pub fn new(
+        nodes: Box<dyn ArrowIpcStreamWriter>,
+        ways: Box<dyn ArrowIpcStreamWriter>,
+        relations: Box<dyn ArrowIpcStreamWriter>,
+    ) -> Self {
+        Self {
+            nodes,
+            ways,
+            relations,
+        }
+    }
+
+    pub fn write(&mut self, elements: &ElementBatches) -> Result<()> {
+        if let Some(nodes) = elements.nodes.as_ref() {
+            self.nodes.write(nodes)?;
+        }
+        if let Some(ways) = elements.ways.as_ref() {
+            self.ways.write(ways)?;
+        }
+        if let Some(relations) = elements.relations.as_ref() {
+            self.relations.write(relations)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self, force: bool) -> Result<Vec<ArrowIpcData>> {
+        let mut data = Vec::new();
+        if force || self.nodes.should_flush() {
+            if let Some((bytes, rows)) = self.nodes.flush()? {
+                data.push(ArrowIpcData::Node(bytes, rows));
+            }
+        }
+        if force || self.ways.should_flush() {
+            if let Some((bytes, rows)) = self.ways.flush()? {
+                data.push(ArrowIpcData::Way(bytes, rows));
+            }
+        }
+        if force || self.relations.should_flush() {
+            if let Some((bytes, rows)) = self.relations.flush()? {
+                data.push(ArrowIpcData::Relation(bytes, rows));
+            }
+        }
+        Ok(data)
+    }
+}
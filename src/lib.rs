@@ -3,6 +3,7 @@ pub mod osmpbf {
 }
 
 pub mod io;
+pub mod ipc;
 pub mod osm;
 pub mod parquet;
 pub mod processor;
@@ -1,10 +1,18 @@
 use crossbeam_channel::bounded;
+use osm_to_parquet::io::{ChecksumAlgorithm, ChecksummingFileWriter, FileWriter, LocalFileWriter};
+use osm_to_parquet::ipc::{ArrowIpcData, ArrowIpcMemoryStreamWriter, OsmArrowIpcStreamWriter};
 use osm_to_parquet::osm::elements::decode_primitive_block;
+use osm_to_parquet::osm::header::OsmHeader;
+use osm_to_parquet::osm::types::ElementCount;
 use osm_to_parquet::osm::{blobs::read_osm_data, elements::OsmData, pbf::PbfReader};
+use osm_to_parquet::osmpbf::PrimitiveBlock;
+use osm_to_parquet::parquet::geo::OsmGeoParquetWriter;
+use osm_to_parquet::parquet::parquet::WriterConfig;
 use osm_to_parquet::parquet::records::ElementBatches;
 use osm_to_parquet::parquet::schemas::{get_node_schema, get_relation_schema, get_way_schema};
 use osm_to_parquet::parquet::writer::{
-    OsmParquetStreamWriter, ParquetData, ParquetMemoryStreamWriter,
+    OsmParquetStreamWriter, ParquetData, ParquetFileConfig, ParquetMemoryStreamWriter,
+    create_writer_options,
 };
 use osm_to_parquet::progress::Progress;
 use rayon;
@@ -12,15 +20,53 @@ use rayon::prelude::*;
 use readable;
 use readable::num;
 use std::fs;
+use std::io::Read;
 use std::path::Path;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 
+/// Output container selected by the `--format` CLI flag (`parquet`, the
+/// default, `arrow` for an Arrow IPC / Feather v2 stream, or `geo` for
+/// GeoParquet nodes/ways with WKB `geometry` columns).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputFormat {
+    Parquet,
+    Arrow,
+    Geo,
+}
+
+impl OutputFormat {
+    fn from_args() -> Self {
+        match std::env::args().nth(1).as_deref() {
+            Some("--format=arrow") => OutputFormat::Arrow,
+            Some("--format=geo") => OutputFormat::Geo,
+            Some("--format=parquet") | None => OutputFormat::Parquet,
+            Some(other) => {
+                panic!("unknown --format value: {other} (expected parquet, arrow, or geo)")
+            }
+        }
+    }
+}
+
 fn main() {
+    let format = OutputFormat::from_args();
+
     //let filename = "/data/osm/nevada-latest.osm.pbf";
     let filename = "/data/osm/us-latest.osm.pbf";
     println!("Processing {}", filename);
-    let pbf = PbfReader::for_local_file(filename).unwrap();
+    let mut pbf = PbfReader::for_local_file(filename).unwrap();
+
+    // The PBF spec guarantees the `OSMHeader` blob is the first one in the file,
+    // so it's read synchronously here, before the blob pool starts, and threaded
+    // into the Parquet and GeoParquet writers below as file metadata (the Arrow
+    // IPC output does not carry it). Not every PBF
+    // honors that guarantee, though, so a first blob that turns out to be a
+    // primitive block is kept rather than discarded, and fed into the element
+    // stream the same as every other blob.
+    let (header, first_block) = match read_osm_data(&pbf.read_blob().unwrap()).unwrap() {
+        OsmData::Header(header_block) => (Some(OsmHeader::from_header_block(&header_block)), None),
+        OsmData::Primitive(block) => (None, Some(block)),
+    };
 
     let progress = Progress::new();
 
@@ -33,9 +79,37 @@ fn main() {
     fs::create_dir_all(root_path.join("ways")).unwrap();
     fs::create_dir_all(root_path.join("relations")).unwrap();
 
+    match format {
+        OutputFormat::Parquet => {
+            run_parquet_pipeline(pbf, header, first_block, root_path, progress)
+        }
+        OutputFormat::Arrow => run_arrow_ipc_pipeline(pbf, first_block, root_path, progress),
+        // `ParquetMultiFileWriter` names its files after the current rayon worker
+        // index, so the sequential geo pipeline runs on a one-thread pool.
+        OutputFormat::Geo => rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap()
+            .install(|| run_geo_pipeline(pbf, header, first_block, root_path, progress)),
+    }
+}
+
+fn run_parquet_pipeline<Source: Read + Send>(
+    pbf: PbfReader<Source>,
+    header: Option<OsmHeader>,
+    first_block: Option<PrimitiveBlock>,
+    root_path: &Path,
+    progress: Progress,
+) {
     let (elements_sender, elements_receiver) = bounded(100);
     let (data_sender, data_receiver) = bounded(10);
 
+    if let Some(block) = first_block {
+        let elements = ElementBatches::from_elements(&decode_primitive_block(&block));
+        progress.inc_elements(elements.count());
+        elements_sender.send(Arc::new(elements)).unwrap();
+    }
+
     let pbf_pool = rayon::ThreadPoolBuilder::new()
         .num_threads(8)
         .build()
@@ -79,18 +153,29 @@ fn main() {
                 let progress = progress.clone();
                 let elements_receiver = elements_receiver.clone();
                 let data_sender = data_sender.clone();
+                let header = header.clone();
                 s.spawn(move |_| {
+                    let node_config = ParquetFileConfig::for_nodes();
+                    let way_config = ParquetFileConfig::for_ways();
+                    let relation_config = ParquetFileConfig::for_relations();
                     let mut writer = OsmParquetStreamWriter::new(
                         Box::new(ParquetMemoryStreamWriter::new(
                             get_node_schema(),
-                            None,
-                            None,
+                            Some(create_writer_options(&node_config)),
+                            Some(node_config),
+                            header.as_ref(),
+                        )),
+                        Box::new(ParquetMemoryStreamWriter::new(
+                            get_way_schema(),
+                            Some(create_writer_options(&way_config)),
+                            Some(way_config),
+                            header.as_ref(),
                         )),
-                        Box::new(ParquetMemoryStreamWriter::new(get_way_schema(), None, None)),
                         Box::new(ParquetMemoryStreamWriter::new(
                             get_relation_schema(),
-                            None,
-                            None,
+                            Some(create_writer_options(&relation_config)),
+                            Some(relation_config),
+                            header.as_ref(),
                         )),
                     );
                     for elements in elements_receiver.iter() {
@@ -110,6 +195,127 @@ fn main() {
             }
             drop(data_sender);
 
+            let writer = Arc::new(ChecksummingFileWriter::new(
+                LocalFileWriter::new(root_path.to_path_buf()),
+                ChecksumAlgorithm::Sha256,
+            ));
+
+            write_pool.scope(|s| {
+                for _ in 0..write_pool.current_num_threads() {
+                    let progress = progress.clone();
+                    let data_receiver = data_receiver.clone();
+                    let writer = writer.clone();
+                    s.spawn(move |_s| {
+                        for data in data_receiver.iter() {
+                            match data {
+                                ParquetData::Node(data, rows) => {
+                                    progress.inc_bytes(data.len());
+                                    writer.write_nodes(&data, rows);
+                                }
+                                ParquetData::Way(data, rows) => {
+                                    progress.inc_bytes(data.len());
+                                    writer.write_ways(&data, rows);
+                                }
+                                ParquetData::Relation(data, rows) => {
+                                    progress.inc_bytes(data.len());
+                                    writer.write_relations(&data, rows);
+                                }
+                            }
+                        }
+                        progress.bytes.finish();
+                    });
+                }
+            });
+
+            writer.finish();
+        });
+    });
+}
+
+/// Arrow IPC counterpart to `run_parquet_pipeline`, selected by `--format=arrow`.
+/// Mirrors the same three-pool blob -> batch -> sink shape, swapping in the
+/// `ArrowIpcData`/`OsmArrowIpcStreamWriter` types and a `.arrow` file extension.
+fn run_arrow_ipc_pipeline<Source: Read + Send>(
+    pbf: PbfReader<Source>,
+    first_block: Option<PrimitiveBlock>,
+    root_path: &Path,
+    progress: Progress,
+) {
+    let (elements_sender, elements_receiver) = bounded(100);
+    let (data_sender, data_receiver) = bounded(10);
+
+    if let Some(block) = first_block {
+        let elements = ElementBatches::from_elements(&decode_primitive_block(&block));
+        progress.inc_elements(elements.count());
+        elements_sender.send(Arc::new(elements)).unwrap();
+    }
+
+    let pbf_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(8)
+        .build()
+        .unwrap();
+
+    let arrow_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(8)
+        .build()
+        .unwrap();
+
+    let write_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(2)
+        .build()
+        .unwrap();
+
+    pbf_pool.scope(|s| {
+        {
+            let progress = progress.clone();
+            s.spawn(move |_| {
+                pbf.into_iter().par_bridge().for_each(|blob| {
+                    progress.inc_pbf(1);
+                    let data = read_osm_data(&blob).unwrap();
+
+                    match data {
+                        OsmData::Primitive(block) => {
+                            let elements =
+                                ElementBatches::from_elements(&decode_primitive_block(&block));
+                            progress.inc_elements(elements.count());
+                            elements_sender.send(Arc::new(elements)).unwrap();
+                        }
+                        _ => {}
+                    }
+                });
+                progress.pbf.finish();
+                progress.elements.finish();
+            });
+        }
+
+        arrow_pool.scope(|s| {
+            for _ in 0..arrow_pool.current_num_threads() {
+                let progress = progress.clone();
+                let elements_receiver = elements_receiver.clone();
+                let data_sender = data_sender.clone();
+                s.spawn(move |_| {
+                    let mut writer = OsmArrowIpcStreamWriter::new(
+                        Box::new(ArrowIpcMemoryStreamWriter::new(get_node_schema(), None)),
+                        Box::new(ArrowIpcMemoryStreamWriter::new(get_way_schema(), None)),
+                        Box::new(ArrowIpcMemoryStreamWriter::new(get_relation_schema(), None)),
+                    );
+                    for elements in elements_receiver.iter() {
+                        writer.write(&elements).unwrap();
+
+                        for data in writer.flush(false).unwrap() {
+                            progress.inc_files(1);
+                            data_sender.send(data).unwrap();
+                        }
+                    }
+                    for data in writer.flush(true).unwrap() {
+                        progress.inc_files(1);
+                        data_sender.send(data).unwrap();
+                    }
+                    progress.files.finish();
+                });
+            }
+            drop(data_sender);
+
             write_pool.scope(|s| {
                 for _ in 0..write_pool.current_num_threads() {
                     let nodes_index = Arc::new(AtomicUsize::new(1));
@@ -120,25 +326,25 @@ fn main() {
                     s.spawn(move |_s| {
                         for data in data_receiver.iter() {
                             match data {
-                                ParquetData::Node(data) => {
+                                ArrowIpcData::Node(data, _rows) => {
                                     let nodes_index = nodes_index.fetch_add(1, Ordering::Relaxed);
                                     let filename = root_path
-                                        .join(format!("nodes/nodes_{nodes_index:06}.parquet"));
+                                        .join(format!("nodes/nodes_{nodes_index:06}.arrow"));
                                     progress.inc_bytes(data.len());
                                     fs::write(filename, data).unwrap();
                                 }
-                                ParquetData::Way(data) => {
+                                ArrowIpcData::Way(data, _rows) => {
                                     let ways_index = ways_index.fetch_add(1, Ordering::Relaxed);
-                                    let filename = root_path
-                                        .join(format!("ways/ways_{ways_index:06}.parquet"));
+                                    let filename =
+                                        root_path.join(format!("ways/ways_{ways_index:06}.arrow"));
                                     progress.inc_bytes(data.len());
                                     fs::write(filename, data).unwrap();
                                 }
-                                ParquetData::Relation(data) => {
+                                ArrowIpcData::Relation(data, _rows) => {
                                     let relations_index =
                                         relations_index.fetch_add(1, Ordering::Relaxed);
                                     let filename = root_path.join(format!(
-                                        "relations/relations_{relations_index:06}.parquet"
+                                        "relations/relations_{relations_index:06}.arrow"
                                     ));
                                     progress.inc_bytes(data.len());
                                     fs::write(filename, data).unwrap();
@@ -152,3 +358,51 @@ fn main() {
         });
     });
 }
+
+/// GeoParquet counterpart to `run_parquet_pipeline`, selected by `--format=geo`.
+/// `OsmGeoParquetWriter` assembles WKB geometry straight off each decoded
+/// `PrimitiveBlock` and rotates its own multi-file output, so unlike the other
+/// two formats there's no `Elements`/`ElementBatches` stage to fan out across a
+/// pool — blobs are decoded and written sequentially.
+fn run_geo_pipeline<Source: Read>(
+    pbf: PbfReader<Source>,
+    header: Option<OsmHeader>,
+    first_block: Option<PrimitiveBlock>,
+    root_path: &Path,
+    progress: Progress,
+) {
+    let mut writer = OsmGeoParquetWriter::new(
+        root_path.to_str().unwrap(),
+        WriterConfig::for_nodes(),
+        WriterConfig::for_ways(),
+        header,
+    );
+
+    if let Some(block) = first_block {
+        progress.inc_pbf(1);
+        let stats = writer.write_elements(&block);
+        progress.inc_elements(ElementCount {
+            nodes: stats.nodes,
+            ways: stats.ways,
+            relations: stats.relations,
+        });
+    }
+
+    for blob in pbf.into_iter() {
+        progress.inc_pbf(1);
+        if let OsmData::Primitive(block) = read_osm_data(&blob).unwrap() {
+            let stats = writer.write_elements(&block);
+            progress.inc_elements(ElementCount {
+                nodes: stats.nodes,
+                ways: stats.ways,
+                relations: stats.relations,
+            });
+        }
+    }
+
+    writer.close();
+    progress.pbf.finish();
+    progress.elements.finish();
+    progress.files.finish();
+    progress.bytes.finish();
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use crate::osmpbf::HeaderBlock;
+
+const NANO: f64 = 0.000000001;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OsmBoundingBox {
+    pub left: f64,
+    pub right: f64,
+    pub top: f64,
+    pub bottom: f64,
+}
+
+/// Decoded `HeaderBlock` provenance, carried alongside a PBF extract so it can be
+/// stamped onto every Parquet file produced from it as schema key-value metadata.
+#[derive(Debug, Clone, Default)]
+pub struct OsmHeader {
+    pub bbox: Option<OsmBoundingBox>,
+    pub writingprogram: Option<String>,
+    pub source: Option<String>,
+    pub required_features: Vec<String>,
+    pub optional_features: Vec<String>,
+    pub replication_timestamp: Option<i64>,
+    pub replication_sequence_number: Option<i64>,
+    pub replication_base_url: Option<String>,
+}
+
+impl OsmHeader {
+    pub fn from_header_block(header: &HeaderBlock) -> Self {
+        Self {
+            bbox: header.bbox.as_ref().map(|bbox| OsmBoundingBox {
+                left: NANO * bbox.left as f64,
+                right: NANO * bbox.right as f64,
+                top: NANO * bbox.top as f64,
+                bottom: NANO * bbox.bottom as f64,
+            }),
+            writingprogram: header.writingprogram.clone(),
+            source: header.source.clone(),
+            required_features: header.required_features.clone(),
+            optional_features: header.optional_features.clone(),
+            replication_timestamp: header.osmosis_replication_timestamp,
+            replication_sequence_number: header.osmosis_replication_sequence_number,
+            replication_base_url: header.osmosis_replication_base_url.clone(),
+        }
+    }
+
+    /// Renders the header as Arrow schema key-value metadata, so it survives
+    /// into the Parquet file footer alongside the serialized Arrow schema.
+    pub fn to_metadata(&self) -> HashMap<String, String> {
+        let mut metadata = HashMap::new();
+
+        if let Some(bbox) = self.bbox {
+            metadata.insert("osm.bbox.left".to_string(), bbox.left.to_string());
+            metadata.insert("osm.bbox.right".to_string(), bbox.right.to_string());
+            metadata.insert("osm.bbox.top".to_string(), bbox.top.to_string());
+            metadata.insert("osm.bbox.bottom".to_string(), bbox.bottom.to_string());
+        }
+        if let Some(writingprogram) = &self.writingprogram {
+            metadata.insert("osm.writingprogram".to_string(), writingprogram.clone());
+        }
+        if let Some(source) = &self.source {
+            metadata.insert("osm.source".to_string(), source.clone());
+        }
+        if !self.required_features.is_empty() {
+            metadata.insert(
+                "osm.required_features".to_string(),
+                self.required_features.join(","),
+            );
+        }
+        if !self.optional_features.is_empty() {
+            metadata.insert(
+                "osm.optional_features".to_string(),
+                self.optional_features.join(","),
+            );
+        }
+        if let Some(timestamp) = self.replication_timestamp {
+            metadata.insert(
+                "osm.replication.timestamp".to_string(),
+                timestamp.to_string(),
+            );
+        }
+        if let Some(sequence_number) = self.replication_sequence_number {
+            metadata.insert(
+                "osm.replication.sequence_number".to_string(),
+                sequence_number.to_string(),
+            );
+        }
+        if let Some(base_url) = &self.replication_base_url {
+            metadata.insert("osm.replication.base_url".to_string(), base_url.clone());
+        }
+
+        metadata
+    }
+}
@@ -5,6 +5,7 @@ use std::{
 };
 
 use crate::{
+    io::ObjectStoreReader,
     osm::blobs::BlobData,
     osmpbf::{Blob, BlobHeader},
 };
@@ -45,6 +46,14 @@ impl PbfReader<BufReader<File>> {
     }
 }
 
+impl PbfReader<ObjectStoreReader> {
+    /// Reads a `.osm.pbf` directly out of object storage (`s3://`, `gs://`,
+    /// `az://`, `file://`, ...) via byte-range GETs, without downloading it whole.
+    pub fn with_url(url: &str) -> Result<Self> {
+        Ok(Self::new(ObjectStoreReader::new(url)?))
+    }
+}
+
 impl<Source: Read> Iterator for PbfReader<Source> {
     type Item = BlobData;
 
@@ -0,0 +1,298 @@
+use std::{collections::HashMap, sync::Arc};
+
+use arrow::{
+    array::{ArrayRef, BinaryBuilder, Int64Builder, RecordBatch},
+    datatypes::Schema,
+};
+
+use crate::{
+    osm::{
+        elements::{PrimitiveBlockDecoder, decode_nodes, decode_ways},
+        header::OsmHeader,
+        types::{OsmNode, OsmWay},
+    },
+    osmpbf::PrimitiveBlock,
+    parquet::{
+        parquet::{ParquetMultiFileWriter, WriteStatistics, WriterConfig},
+        records::{InfoBuilder, TagsBuilder},
+        schemas::{get_node_geo_schema, get_way_geo_schema},
+    },
+};
+
+fn wkb_point(lon: f64, lat: f64) -> Vec<u8> {
+    let mut wkb = Vec::with_capacity(21);
+    wkb.push(1); // byte order: little endian
+    wkb.extend_from_slice(&1u32.to_le_bytes()); // wkbPoint
+    wkb.extend_from_slice(&lon.to_le_bytes());
+    wkb.extend_from_slice(&lat.to_le_bytes());
+    wkb
+}
+
+fn wkb_line_string(coords: &[(f64, f64)]) -> Vec<u8> {
+    let mut wkb = Vec::with_capacity(9 + coords.len() * 16);
+    wkb.push(1);
+    wkb.extend_from_slice(&2u32.to_le_bytes()); // wkbLineString
+    wkb.extend_from_slice(&(coords.len() as u32).to_le_bytes());
+    for (lon, lat) in coords {
+        wkb.extend_from_slice(&lon.to_le_bytes());
+        wkb.extend_from_slice(&lat.to_le_bytes());
+    }
+    wkb
+}
+
+fn wkb_polygon(ring: &[(f64, f64)]) -> Vec<u8> {
+    let mut wkb = Vec::with_capacity(13 + ring.len() * 16);
+    wkb.push(1);
+    wkb.extend_from_slice(&3u32.to_le_bytes()); // wkbPolygon
+    wkb.extend_from_slice(&1u32.to_le_bytes()); // one ring, no holes
+    wkb.extend_from_slice(&(ring.len() as u32).to_le_bytes());
+    for (lon, lat) in ring {
+        wkb.extend_from_slice(&lon.to_le_bytes());
+        wkb.extend_from_slice(&lat.to_le_bytes());
+    }
+    wkb
+}
+
+/// Resolves way `nodes` id lists to coordinates during the streaming write.
+///
+/// Relies on planet extracts always ordering node blocks before way blocks, so
+/// the index is complete by the time the first way arrives. For planet-scale
+/// inputs this map should be replaced with a disk-backed or memory-mapped store;
+/// it stays in-memory here since that's the dominant case for country-sized extracts.
+#[derive(Debug, Default)]
+pub struct NodeCoordinateIndex {
+    coordinates: HashMap<i64, (f64, f64)>,
+}
+
+impl NodeCoordinateIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn index_nodes(&mut self, nodes: &[Arc<OsmNode>]) {
+        self.coordinates.reserve(nodes.len());
+        for node in nodes {
+            self.coordinates
+                .insert(node.id, (node.longitude, node.latitude));
+        }
+    }
+
+    pub fn get(&self, id: i64) -> Option<(f64, f64)> {
+        self.coordinates.get(&id).copied()
+    }
+}
+
+pub fn create_geo_batch_for_nodes(
+    nodes: &[Arc<OsmNode>],
+    schema: Arc<Schema>,
+) -> Option<RecordBatch> {
+    if nodes.is_empty() {
+        return None;
+    }
+
+    let mut id = Int64Builder::with_capacity(nodes.len());
+    let mut tags = TagsBuilder::with_capacity(nodes.len());
+    let mut geometry = BinaryBuilder::new();
+    let mut info = InfoBuilder::with_capacity(nodes.len());
+
+    for node in nodes {
+        id.append_value(node.id);
+        tags.append(&node.tags);
+        geometry.append_value(wkb_point(node.longitude, node.latitude));
+        info.append(&node.info);
+    }
+
+    let columns = vec![
+        Arc::new(id.finish()) as ArrayRef,
+        Arc::new(info.version.finish()) as ArrayRef,
+        Arc::new(tags.finish()) as ArrayRef,
+        Arc::new(geometry.finish()) as ArrayRef,
+        Arc::new(info.timestamp.finish()) as ArrayRef,
+        Arc::new(info.changeset.finish()) as ArrayRef,
+        Arc::new(info.uid.finish()) as ArrayRef,
+        Arc::new(info.user_sid.finish()) as ArrayRef,
+    ];
+    Some(RecordBatch::try_new(schema, columns).unwrap())
+}
+
+/// Encodes each way as a WKB LineString, or a closed-ring Polygon when the
+/// resolved first and last coordinates match (the common "area" convention
+/// in OSM ways). Node ids that aren't in `index` are skipped; referenced
+/// nodes streamed from an extract boundary across tiles are simply dropped
+/// from the geometry, so closure is checked on `coords` (post-filter), not
+/// on `way.nodes` ids — a dropped boundary node must not be mistaken for a
+/// closed ring.
+pub fn create_geo_batch_for_ways(
+    ways: &[Arc<OsmWay>],
+    index: &NodeCoordinateIndex,
+    schema: Arc<Schema>,
+) -> Option<RecordBatch> {
+    if ways.is_empty() {
+        return None;
+    }
+
+    let mut id = Int64Builder::with_capacity(ways.len());
+    let mut tags = TagsBuilder::with_capacity(ways.len());
+    let mut geometry = BinaryBuilder::new();
+    let mut info = InfoBuilder::with_capacity(ways.len());
+
+    for way in ways {
+        id.append_value(way.id);
+        tags.append(&way.tags);
+
+        let coords: Vec<(f64, f64)> = way
+            .nodes
+            .iter()
+            .filter_map(|node_id| index.get(*node_id))
+            .collect();
+        let is_closed_ring = coords.len() >= 4 && coords.first() == coords.last();
+        let wkb = if is_closed_ring {
+            wkb_polygon(&coords)
+        } else {
+            wkb_line_string(&coords)
+        };
+        geometry.append_value(wkb);
+        info.append(&way.info);
+    }
+
+    let columns = vec![
+        Arc::new(id.finish()) as ArrayRef,
+        Arc::new(info.version.finish()) as ArrayRef,
+        Arc::new(tags.finish()) as ArrayRef,
+        Arc::new(geometry.finish()) as ArrayRef,
+        Arc::new(info.timestamp.finish()) as ArrayRef,
+        Arc::new(info.changeset.finish()) as ArrayRef,
+        Arc::new(info.uid.finish()) as ArrayRef,
+        Arc::new(info.user_sid.finish()) as ArrayRef,
+    ];
+    Some(RecordBatch::try_new(schema, columns).unwrap())
+}
+
+/// Builds the GeoParquet `geo` file metadata value: a JSON object naming the
+/// primary geometry column, its encoding, the geometry types present, and the
+/// CRS (GeoParquet defaults to `OGC:CRS84`, i.e. lon/lat WGS84). `geometry_types`
+/// lists every WKB type the column can actually hold, e.g. ways mix `LineString`
+/// and `Polygon` depending on whether a given way's ring closes.
+fn geo_metadata_json(geometry_types: &[&str], bbox: Option<&(f64, f64, f64, f64)>) -> String {
+    let bbox_json = match bbox {
+        Some((left, bottom, right, top)) => {
+            format!("[{left}, {bottom}, {right}, {top}]")
+        }
+        None => "null".to_string(),
+    };
+    let types_json = geometry_types
+        .iter()
+        .map(|geometry_type| format!("\"{geometry_type}\""))
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        "{{\"version\":\"1.0.0\",\"primary_column\":\"geometry\",\"columns\":{{\"geometry\":{{\"encoding\":\"WKB\",\"geometry_types\":[{types_json}],\"crs\":\"OGC:CRS84\"}}}},\"bbox\":{bbox_json}}}"
+    )
+}
+
+/// Merges the decoded `HeaderBlock` metadata (if any) with the GeoParquet `geo`
+/// key and returns a schema carrying both, ready to hand to `ParquetMultiFileWriter`.
+pub fn geo_schema_with_metadata(
+    schema: Arc<Schema>,
+    geometry_types: &[&str],
+    header: Option<&OsmHeader>,
+) -> Arc<Schema> {
+    let bbox = header.and_then(|header| {
+        header
+            .bbox
+            .map(|bbox| (bbox.left, bbox.bottom, bbox.right, bbox.top))
+    });
+
+    let mut metadata: HashMap<String, String> = header
+        .map(|header| header.to_metadata())
+        .unwrap_or_default();
+    metadata.insert(
+        "geo".to_string(),
+        geo_metadata_json(geometry_types, bbox.as_ref()),
+    );
+
+    Arc::new(schema.as_ref().clone().with_metadata(metadata))
+}
+
+/// GeoParquet writer variant of [`crate::parquet::parquet::OsmParquetWriter`]: emits
+/// `geometry` (WKB) columns for nodes and ways instead of the flat lat/lon/nodes
+/// columns. Relations have no single well-defined geometry assembly rule in OSM
+/// (routes, multipolygons, ...), so this variant only covers nodes and ways.
+pub struct OsmGeoParquetWriter {
+    nodes: ParquetMultiFileWriter,
+    ways: ParquetMultiFileWriter,
+    node_index: NodeCoordinateIndex,
+}
+
+impl OsmGeoParquetWriter {
+    pub fn new(
+        root_path: &str,
+        node_config: WriterConfig,
+        way_config: WriterConfig,
+        header: Option<OsmHeader>,
+    ) -> Self {
+        let node_schema =
+            geo_schema_with_metadata(get_node_geo_schema(), &["Point"], header.as_ref());
+        let way_schema = geo_schema_with_metadata(
+            get_way_geo_schema(),
+            &["LineString", "Polygon"],
+            header.as_ref(),
+        );
+        Self {
+            nodes: ParquetMultiFileWriter::new(
+                &format!("{root_path}/nodes"),
+                node_schema,
+                node_config,
+                None,
+            ),
+            ways: ParquetMultiFileWriter::new(
+                &format!("{root_path}/ways"),
+                way_schema,
+                way_config,
+                None,
+            ),
+            node_index: NodeCoordinateIndex::new(),
+        }
+    }
+
+    /// Relies on the PBF stream ordering nodes before ways (the osmium/osmosis
+    /// convention for planet and extract dumps), so `node_index` is complete by
+    /// the time the first way block is decoded.
+    pub fn write_elements(&mut self, block: &PrimitiveBlock) -> WriteStatistics {
+        let decoder = PrimitiveBlockDecoder::new(&block);
+
+        let nodes = decode_nodes(&block, &decoder);
+        if !nodes.is_empty() {
+            self.node_index.index_nodes(&nodes);
+            if let Some(batch) = create_geo_batch_for_nodes(&nodes, self.nodes.schema()) {
+                self.nodes.write(&batch);
+            }
+        }
+
+        let ways = decode_ways(&block, &decoder);
+        if !ways.is_empty() {
+            if let Some(batch) =
+                create_geo_batch_for_ways(&ways, &self.node_index, self.ways.schema())
+            {
+                self.ways.write(&batch);
+            }
+        }
+
+        WriteStatistics {
+            nodes: nodes.len(),
+            ways: ways.len(),
+            relations: 0,
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.nodes.close();
+        self.ways.close();
+    }
+}
+
+impl Drop for OsmGeoParquetWriter {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
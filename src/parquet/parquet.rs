@@ -6,24 +6,44 @@ use std::{
 };
 
 use arrow::{array::RecordBatch, datatypes::Schema};
-use parquet::arrow::ArrowWriter;
+use parquet::{
+    arrow::ArrowWriter,
+    basic::Compression,
+    file::properties::EnabledStatistics,
+    schema::types::ColumnPath,
+};
 
 use crate::{
     osm::elements::{PrimitiveBlockDecoder, decode_nodes, decode_relations, decode_ways},
+    osm::header::OsmHeader,
     osmpbf::PrimitiveBlock,
     parquet::{
-        records::{convert_nodes, convert_relations, convert_ways},
+        records::{convert_nodes, convert_relations, convert_ways, sort_batch_by_column},
         schemas::{get_node_schema, get_relation_schema, get_way_schema},
     },
 };
 use rayon;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct WriterConfig {
     max_row_group_size: Option<usize>,
     max_rows_per_file: Option<usize>,
     max_file_size_bytes: Option<usize>,
     buffer_size: usize,
+    compression: Compression,
+    /// Enables page-level statistics and the column/offset index structures,
+    /// so readers can skip pages instead of whole row groups.
+    page_index: bool,
+    /// Column to sort each incoming batch by before writing, tightening the
+    /// per-page min/max bounds the page index records. Off by default since
+    /// sorting costs CPU the caller may not want to pay.
+    sort_by: Option<String>,
+    /// Leaf column paths (e.g. "id", "nodes.list.item") to build a split-block
+    /// bloom filter for, so point lookups can skip row groups without decoding them.
+    bloom_filter_columns: Vec<String>,
+    /// Target false-positive probability for the bloom filters above. `None`
+    /// keeps the parquet crate's own default.
+    bloom_filter_fpp: Option<f64>,
 }
 
 impl WriterConfig {
@@ -33,8 +53,61 @@ impl WriterConfig {
             max_rows_per_file: None,
             max_file_size_bytes: Some(128 * 1024 * 1024),
             buffer_size: 8 * 1024 * 1024,
+            compression: Compression::SNAPPY,
+            page_index: true,
+            sort_by: None,
+            bloom_filter_columns: Vec::new(),
+            bloom_filter_fpp: None,
         }
     }
+
+    /// Config for the node multi-file writer: bloom filter on `id` for fast point lookups.
+    pub fn for_nodes() -> Self {
+        Self::new().with_bloom_filter_columns(vec!["id".to_string()])
+    }
+
+    /// Config for the way multi-file writer: bloom filters on `id` and the node ref list,
+    /// since resolving way geometry means probing every referenced node id.
+    pub fn for_ways() -> Self {
+        Self::new().with_bloom_filter_columns(vec!["id".to_string(), "nodes.list.item".to_string()])
+    }
+
+    /// Config for the relation multi-file writer: bloom filters on `id` and member ids.
+    pub fn for_relations() -> Self {
+        Self::new()
+            .with_bloom_filter_columns(vec!["id".to_string(), "members.list.item.id".to_string()])
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_page_index(mut self, page_index: bool) -> Self {
+        self.page_index = page_index;
+        self
+    }
+
+    pub fn with_sort_by(mut self, sort_by: Option<String>) -> Self {
+        self.sort_by = sort_by;
+        self
+    }
+
+    pub fn with_bloom_filter_columns(mut self, bloom_filter_columns: Vec<String>) -> Self {
+        self.bloom_filter_columns = bloom_filter_columns;
+        self
+    }
+
+    pub fn with_bloom_filter_fpp(mut self, bloom_filter_fpp: f64) -> Self {
+        self.bloom_filter_fpp = Some(bloom_filter_fpp);
+        self
+    }
+}
+
+impl Default for WriterConfig {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub fn create_parquet_writer<Target: Write + Send>(
@@ -42,12 +115,25 @@ pub fn create_parquet_writer<Target: Write + Send>(
     schema: Arc<Schema>,
     writer_config: WriterConfig,
 ) -> ArrowWriter<Target> {
-    let mut builder = parquet::file::properties::WriterProperties::builder();
+    let mut builder = parquet::file::properties::WriterProperties::builder()
+        .set_compression(writer_config.compression);
 
     if let Some(max_row_group_size) = writer_config.max_row_group_size {
         builder = builder.set_max_row_group_size(max_row_group_size);
     }
 
+    if writer_config.page_index {
+        builder = builder.set_statistics_enabled(EnabledStatistics::Page);
+    }
+
+    for column in &writer_config.bloom_filter_columns {
+        let path = ColumnPath::from(column.as_str());
+        builder = builder.set_column_bloom_filter_enabled(path.clone(), true);
+        if let Some(fpp) = writer_config.bloom_filter_fpp {
+            builder = builder.set_column_bloom_filter_fpp(path, fpp);
+        }
+    }
+
     let props = builder.build();
     ArrowWriter::try_new(target, schema, Some(props)).unwrap()
 }
@@ -71,21 +157,77 @@ impl fmt::Display for WriteStatistics {
     }
 }
 
-pub struct ParquetMultiFileWriter {
+/// Produces a fresh write sink each time [`ParquetMultiFileWriter`] rotates to a new
+/// output file. `key` is the sink-rotation key from `next_sink_key` (by default a
+/// generated filename), so an object-store factory can turn it into a multipart
+/// upload key instead of a local path.
+pub trait SinkFactory {
+    type Sink: Write + Send;
+    fn create_sink(&mut self, key: &str) -> Self::Sink;
+}
+
+/// Default [`SinkFactory`]: writes each rotated file under `root_path` on the
+/// local filesystem, preserving `ParquetMultiFileWriter`'s original behavior.
+pub struct LocalSinkFactory {
     root_path: String,
+    buffer_size: usize,
+}
+
+impl LocalSinkFactory {
+    pub fn new(root_path: &str, buffer_size: usize) -> Self {
+        create_dir_all(root_path).unwrap();
+        Self {
+            root_path: root_path.to_string(),
+            buffer_size,
+        }
+    }
+}
+
+impl SinkFactory for LocalSinkFactory {
+    type Sink = BufWriter<File>;
+
+    fn create_sink(&mut self, key: &str) -> Self::Sink {
+        let f = File::create(format!("{}/{key}", self.root_path)).unwrap();
+        BufWriter::with_capacity(self.buffer_size, f)
+    }
+}
+
+pub struct ParquetMultiFileWriter<F: SinkFactory = LocalSinkFactory> {
+    factory: F,
     schema: Arc<Schema>,
     writer_config: WriterConfig,
-    writer: Option<ArrowWriter<BufWriter<File>>>,
+    writer: Option<ArrowWriter<F::Sink>>,
     file_index: usize,
     num_rows: usize,
 }
 
-impl ParquetMultiFileWriter {
-    pub fn new(path: &str, schema: Arc<Schema>, writer_config: WriterConfig) -> Self {
-        create_dir_all(path).unwrap();
-        Self {
-            root_path: path.to_string(),
+impl ParquetMultiFileWriter<LocalSinkFactory> {
+    pub fn new(
+        path: &str,
+        schema: Arc<Schema>,
+        writer_config: WriterConfig,
+        header: Option<&OsmHeader>,
+    ) -> Self {
+        let factory = LocalSinkFactory::new(path, writer_config.buffer_size);
+        Self::with_factory(factory, schema, writer_config, header)
+    }
+}
 
+impl<F: SinkFactory> ParquetMultiFileWriter<F> {
+    /// Same rotation behavior as [`ParquetMultiFileWriter::new`], but sinks come
+    /// from `factory` instead of the local filesystem (e.g. an object-store upload).
+    pub fn with_factory(
+        factory: F,
+        schema: Arc<Schema>,
+        writer_config: WriterConfig,
+        header: Option<&OsmHeader>,
+    ) -> Self {
+        let schema = match header {
+            Some(header) => Arc::new(schema.as_ref().clone().with_metadata(header.to_metadata())),
+            None => schema,
+        };
+        Self {
+            factory,
             schema,
             writer_config,
             writer: None,
@@ -98,13 +240,10 @@ impl ParquetMultiFileWriter {
         self.schema.clone()
     }
 
-    fn get_next_filename(&mut self) -> String {
+    fn next_sink_key(&mut self) -> String {
         self.file_index += 1;
         let id = rayon::current_thread_index().unwrap() + 1;
-        format!(
-            "{}/data_{:0>4}_{:0>6}.parquet",
-            self.root_path, id, self.file_index
-        )
+        format!("data_{:0>4}_{:0>6}.parquet", id, self.file_index)
     }
 
     fn should_switch_writer(&self) -> bool {
@@ -128,10 +267,10 @@ impl ParquetMultiFileWriter {
     fn change_writer(&mut self) {
         self.close();
 
-        let f = File::create(&self.get_next_filename()).unwrap();
-        let b = BufWriter::with_capacity(self.writer_config.buffer_size, f);
+        let key = self.next_sink_key();
+        let sink = self.factory.create_sink(&key);
         self.writer = Some(create_parquet_writer(
-            b,
+            sink,
             self.schema.clone(),
             self.writer_config.clone(),
         ));
@@ -146,7 +285,10 @@ impl ParquetMultiFileWriter {
             self.change_writer();
         }
         let writer = self.writer.as_mut().unwrap();
-        writer.write(&record).unwrap();
+        match &self.writer_config.sort_by {
+            Some(column) => writer.write(&sort_batch_by_column(record, column)).unwrap(),
+            None => writer.write(&record).unwrap(),
+        }
         self.num_rows += record.num_rows();
     }
 
@@ -173,22 +315,26 @@ impl OsmParquetWriter {
         node_config: WriterConfig,
         way_config: WriterConfig,
         relation_config: WriterConfig,
+        header: Option<OsmHeader>,
     ) -> Self {
         Self {
             nodes: ParquetMultiFileWriter::new(
                 &format!("{root_path}/nodes"),
                 get_node_schema(),
                 node_config,
+                header.as_ref(),
             ),
             ways: ParquetMultiFileWriter::new(
                 &format!("{root_path}/ways"),
                 get_way_schema(),
                 way_config,
+                header.as_ref(),
             ),
             relations: ParquetMultiFileWriter::new(
                 &format!("{root_path}/relations"),
                 get_relation_schema(),
                 relation_config,
+                header.as_ref(),
             ),
         }
     }
@@ -12,15 +12,16 @@ use arrow::{
         ArrayRef, Float64Builder, Int32Builder, Int64Builder, ListBuilder, MapBuilder, RecordBatch,
         StringBuilder, StructBuilder,
     },
+    compute::{sort_to_indices, take},
     datatypes::{DataType, Field, Schema},
 };
 
-struct InfoBuilder {
-    version: Int32Builder,
-    timestamp: Int64Builder,
-    changeset: Int64Builder,
-    uid: Int64Builder,
-    user_sid: StringBuilder,
+pub(crate) struct InfoBuilder {
+    pub(crate) version: Int32Builder,
+    pub(crate) timestamp: Int64Builder,
+    pub(crate) changeset: Int64Builder,
+    pub(crate) uid: Int64Builder,
+    pub(crate) user_sid: StringBuilder,
 }
 
 impl InfoBuilder {
@@ -67,7 +68,7 @@ impl InfoBuilder {
     }
 }
 
-struct TagsBuilder {
+pub(crate) struct TagsBuilder {
     builder: MapBuilder<StringBuilder, StringBuilder>,
 }
 
@@ -237,6 +238,21 @@ pub fn create_batch_for_relations(
     Some(RecordBatch::try_new(schema, columns).unwrap())
 }
 
+/// Reorders every column in `batch` by ascending values of `column`, so that the
+/// per-page min/max statistics Parquet writes are tight around contiguous id ranges.
+pub fn sort_batch_by_column(batch: &RecordBatch, column: &str) -> RecordBatch {
+    let sort_column = batch
+        .column_by_name(column)
+        .unwrap_or_else(|| panic!("no column named {column} to sort by"));
+    let indices = sort_to_indices(sort_column, None, None).unwrap();
+    let columns = batch
+        .columns()
+        .iter()
+        .map(|c| take(c, &indices, None).unwrap())
+        .collect();
+    RecordBatch::try_new(batch.schema(), columns).unwrap()
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct ElementBatches {
     pub nodes: Option<RecordBatch>,
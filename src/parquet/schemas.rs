@@ -4,7 +4,7 @@ use arrow::datatypes::{DataType, Field, Schema};
 
 use crate::osm::types::{OsmNode, OsmRelation, OsmWay};
 
-fn get_tags_field() -> Field {
+pub(crate) fn get_tags_field() -> Field {
     Field::new_map(
         "tags",
         "entries",
@@ -70,6 +70,50 @@ pub fn create_relation_schema() -> Arc<Schema> {
     Arc::new(Schema::new(fields))
 }
 
+/// GeoParquet variant of the node schema: a WKB `geometry` column replaces the
+/// bare `latitude`/`longitude` pair.
+pub fn create_node_geo_schema() -> Arc<Schema> {
+    let fields = vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("version", DataType::Int32, true),
+        get_tags_field(),
+        Field::new("geometry", DataType::Binary, false),
+        Field::new("timestamp", DataType::Int64, true),
+        Field::new("changeset", DataType::Int64, true),
+        Field::new("uid", DataType::Int64, true),
+        Field::new("user_sid", DataType::Utf8, true),
+    ];
+    Arc::new(Schema::new(fields))
+}
+
+/// GeoParquet variant of the way schema: a WKB `geometry` column (LineString, or
+/// Polygon for closed rings) replaces the bare `nodes` id list.
+pub fn create_way_geo_schema() -> Arc<Schema> {
+    let fields = vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("version", DataType::Int32, true),
+        get_tags_field(),
+        Field::new("geometry", DataType::Binary, false),
+        Field::new("timestamp", DataType::Int64, true),
+        Field::new("changeset", DataType::Int64, true),
+        Field::new("uid", DataType::Int64, true),
+        Field::new("user_sid", DataType::Utf8, true),
+    ];
+    Arc::new(Schema::new(fields))
+}
+
+pub fn get_node_geo_schema() -> Arc<Schema> {
+    static SCHEMA: OnceLock<Arc<Schema>> = OnceLock::new();
+    let s = SCHEMA.get_or_init(|| create_node_geo_schema());
+    s.clone()
+}
+
+pub fn get_way_geo_schema() -> Arc<Schema> {
+    static SCHEMA: OnceLock<Arc<Schema>> = OnceLock::new();
+    let s = SCHEMA.get_or_init(|| create_way_geo_schema());
+    s.clone()
+}
+
 pub fn get_node_schema() -> Arc<Schema> {
     static SCHEMA: OnceLock<Arc<Schema>> = OnceLock::new();
     let s = SCHEMA.get_or_init(|| create_node_schema());
@@ -11,21 +11,47 @@ use arrow::{array::RecordBatch, datatypes::Schema};
 use parquet::{
     arrow::ArrowWriter,
     basic::{Compression, ZstdLevel},
-    file::properties::WriterProperties,
+    file::properties::{EnabledStatistics, WriterProperties},
+    schema::types::ColumnPath,
 };
 
-use crate::parquet::records::ElementBatches;
+use crate::osm::header::OsmHeader;
+use crate::parquet::records::{ElementBatches, sort_batch_by_column};
 
+/// `usize` alongside each blob is its row count, carried through so a wrapping
+/// `FileWriter` (e.g. `ChecksummingFileWriter`) can record it in the integrity
+/// manifest without having to re-decode the Parquet footer to find out.
 #[derive(Debug)]
 pub enum ParquetData {
-    Node(Bytes),
-    Way(Bytes),
-    Relation(Bytes),
+    Node(Bytes, usize),
+    Way(Bytes, usize),
+    Relation(Bytes, usize),
 }
 
-pub fn create_writer_options() -> WriterProperties {
-    let props = WriterProperties::builder();
-    props.set_compression(Compression::SNAPPY).build()
+pub fn create_writer_options(config: &ParquetFileConfig) -> WriterProperties {
+    let mut builder = WriterProperties::builder()
+        .set_compression(config.compression)
+        .set_dictionary_enabled(config.dictionary_enabled);
+    if let Some(max_row_group_size) = config.max_row_group_size {
+        builder = builder.set_max_row_group_size(max_row_group_size);
+    }
+    if config.page_index {
+        builder = builder.set_statistics_enabled(EnabledStatistics::Page);
+    }
+    for column in &config.bloom_filter_columns {
+        let path = ColumnPath::from(column.as_str());
+        builder = builder.set_column_bloom_filter_enabled(path.clone(), true);
+        if let Some(fpp) = config.bloom_filter_fpp {
+            builder = builder.set_column_bloom_filter_fpp(path, fpp);
+        }
+    }
+    builder.build()
+}
+
+/// Convenience constructor for a leveled ZSTD codec, since `Compression::ZSTD`
+/// takes a validated `ZstdLevel` rather than a bare integer.
+pub fn zstd_compression(level: i32) -> Compression {
+    Compression::ZSTD(ZstdLevel::try_new(level).unwrap())
 }
 
 pub fn create_parquet_writer<Target: Write + Send>(
@@ -54,10 +80,30 @@ pub fn create_parquet_memory_writer(
     create_parquet_writer(buf, schema, props)
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone)]
 pub struct ParquetFileConfig {
     max_rows_per_file: Option<usize>,
     max_file_size_bytes: Option<usize>,
+    compression: Compression,
+    /// Row count at which the Arrow writer starts a new row group within a file.
+    /// `None` keeps the parquet crate's own default.
+    max_row_group_size: Option<usize>,
+    /// Dictionary-encodes columns when it's likely to help (the parquet crate's
+    /// own heuristic). Off gives up those smaller files for a bit less CPU on write.
+    dictionary_enabled: bool,
+    /// Enables page-level statistics and the column/offset index structures,
+    /// so readers can skip pages instead of whole row groups.
+    page_index: bool,
+    /// Column to sort each incoming batch by before writing, tightening the
+    /// per-page min/max bounds the page index records. Off by default since
+    /// sorting costs CPU the caller may not want to pay.
+    sort_by: Option<String>,
+    /// Leaf column paths (e.g. "id", "nodes.list.item") to build a split-block
+    /// bloom filter for, so point lookups can skip row groups without decoding them.
+    bloom_filter_columns: Vec<String>,
+    /// Target false-positive probability for the bloom filters above. `None`
+    /// keeps the parquet crate's own default.
+    bloom_filter_fpp: Option<f64>,
 }
 
 impl ParquetFileConfig {
@@ -65,8 +111,83 @@ impl ParquetFileConfig {
         Self {
             max_rows_per_file: None,
             max_file_size_bytes: Some(128 * 1024 * 1024),
+            compression: Compression::SNAPPY,
+            max_row_group_size: None,
+            dictionary_enabled: true,
+            page_index: true,
+            sort_by: None,
+            bloom_filter_columns: Vec::new(),
+            bloom_filter_fpp: None,
         }
     }
+
+    /// Config for the node stream writer: bloom filter on `id` for fast point lookups.
+    pub fn for_nodes() -> Self {
+        Self::new().with_bloom_filter_columns(vec!["id".to_string()])
+    }
+
+    /// Config for the way stream writer: bloom filters on `id` and the node ref list,
+    /// since resolving way geometry means probing every referenced node id.
+    pub fn for_ways() -> Self {
+        Self::new().with_bloom_filter_columns(vec!["id".to_string(), "nodes.list.item".to_string()])
+    }
+
+    /// Config for the relation stream writer: bloom filters on `id` and member ids.
+    pub fn for_relations() -> Self {
+        Self::new()
+            .with_bloom_filter_columns(vec!["id".to_string(), "members.list.item.id".to_string()])
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    pub fn with_max_row_group_size(mut self, max_row_group_size: Option<usize>) -> Self {
+        self.max_row_group_size = max_row_group_size;
+        self
+    }
+
+    pub fn with_dictionary_enabled(mut self, dictionary_enabled: bool) -> Self {
+        self.dictionary_enabled = dictionary_enabled;
+        self
+    }
+
+    pub fn with_max_rows_per_file(mut self, max_rows_per_file: Option<usize>) -> Self {
+        self.max_rows_per_file = max_rows_per_file;
+        self
+    }
+
+    pub fn with_max_file_size_bytes(mut self, max_file_size_bytes: Option<usize>) -> Self {
+        self.max_file_size_bytes = max_file_size_bytes;
+        self
+    }
+
+    pub fn with_page_index(mut self, page_index: bool) -> Self {
+        self.page_index = page_index;
+        self
+    }
+
+    pub fn with_bloom_filter_columns(mut self, bloom_filter_columns: Vec<String>) -> Self {
+        self.bloom_filter_columns = bloom_filter_columns;
+        self
+    }
+
+    pub fn with_bloom_filter_fpp(mut self, bloom_filter_fpp: f64) -> Self {
+        self.bloom_filter_fpp = Some(bloom_filter_fpp);
+        self
+    }
+
+    pub fn with_sort_by(mut self, sort_by: Option<String>) -> Self {
+        self.sort_by = sort_by;
+        self
+    }
+}
+
+impl Default for ParquetFileConfig {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 pub trait ParquetStreamWriter {
@@ -75,7 +196,8 @@ pub trait ParquetStreamWriter {
     fn num_rows(&self) -> usize;
     fn num_bytes(&self) -> usize;
     fn should_flush(&self) -> bool;
-    fn flush(&mut self) -> Result<Option<Bytes>>;
+    /// Returns the flushed file's bytes paired with the row count written to it.
+    fn flush(&mut self) -> Result<Option<(Bytes, usize)>>;
 }
 
 pub struct ParquetMemoryStreamWriter {
@@ -87,15 +209,25 @@ pub struct ParquetMemoryStreamWriter {
 }
 
 impl ParquetMemoryStreamWriter {
+    /// `header`, when given, is merged onto `schema` as key-value metadata so the
+    /// decoded `HeaderBlock` provenance survives into every file this writer
+    /// produces, the same way [`crate::parquet::parquet::ParquetMultiFileWriter`]
+    /// stamps it on its own schema.
     pub fn new(
         schema: Arc<Schema>,
         props: Option<WriterProperties>,
         config: Option<ParquetFileConfig>,
+        header: Option<&OsmHeader>,
     ) -> Self {
+        let config = config.unwrap_or(ParquetFileConfig::new());
+        let schema = match header {
+            Some(header) => Arc::new(schema.as_ref().clone().with_metadata(header.to_metadata())),
+            None => schema,
+        };
         Self {
             schema: schema,
-            props: props.or(Some(create_writer_options())),
-            config: config.unwrap_or(ParquetFileConfig::new()),
+            props: props.or(Some(create_writer_options(&config))),
+            config: config,
             writer: None,
             num_rows: 0,
         }
@@ -115,7 +247,10 @@ impl ParquetStreamWriter for ParquetMemoryStreamWriter {
             )?);
         }
         let writer = self.writer.as_mut().unwrap();
-        writer.write(&record).unwrap();
+        match &self.config.sort_by {
+            Some(column) => writer.write(&sort_batch_by_column(record, column)).unwrap(),
+            None => writer.write(&record).unwrap(),
+        }
         self.num_rows += record.num_rows();
         Ok(())
     }
@@ -145,15 +280,16 @@ impl ParquetStreamWriter for ParquetMemoryStreamWriter {
         false
     }
 
-    fn flush(&mut self) -> Result<Option<Bytes>> {
+    fn flush(&mut self) -> Result<Option<(Bytes, usize)>> {
         if let Some(writer) = self.writer.take() {
             let bytes = writer.into_inner()?;
+            let rows = self.num_rows;
             self.num_rows = 0;
 
             if bytes.is_empty() {
                 return Ok(None);
             }
-            return Ok(Some(Bytes::from(bytes)));
+            return Ok(Some((Bytes::from(bytes), rows)));
         }
         return Ok(None);
     }
@@ -194,18 +330,18 @@ impl OsmParquetStreamWriter {
     pub fn flush(&mut self, force: bool) -> Result<Vec<ParquetData>> {
         let mut data = Vec::new();
         if force || self.nodes.should_flush() {
-            if let Some(nodes) = self.nodes.flush()? {
-                data.push(ParquetData::Node(nodes));
+            if let Some((bytes, rows)) = self.nodes.flush()? {
+                data.push(ParquetData::Node(bytes, rows));
             }
         }
         if force || self.ways.should_flush() {
-            if let Some(ways) = self.ways.flush()? {
-                data.push(ParquetData::Way(ways));
+            if let Some((bytes, rows)) = self.ways.flush()? {
+                data.push(ParquetData::Way(bytes, rows));
             }
         }
         if force || self.relations.should_flush() {
-            if let Some(relations) = self.relations.flush()? {
-                data.push(ParquetData::Relation(relations));
+            if let Some((bytes, rows)) = self.relations.flush()? {
+                data.push(ParquetData::Relation(bytes, rows));
             }
         }
         Ok(data)
@@ -5,12 +5,17 @@ use crossbeam_channel::{Receiver, Sender};
 use indicatif::ProgressBar;
 
 use crate::io::FileWriter;
+use crate::ipc::{ArrowIpcData, ArrowIpcMemoryStreamWriter, OsmArrowIpcStreamWriter};
 use crate::osm::blobs::{BlobData, read_osm_data};
 use crate::osm::elements::{OsmData, decode_primitive_block};
+use crate::osm::header::OsmHeader;
 use crate::osm::pbf::PbfReader;
 use crate::parquet::records::Elements;
 use crate::parquet::schemas::{get_node_schema, get_relation_schema, get_way_schema};
-use crate::parquet::writer::{OsmParquetStreamWriter, ParquetData, ParquetMemoryStreamWriter};
+use crate::parquet::writer::{
+    OsmParquetStreamWriter, ParquetData, ParquetFileConfig, ParquetMemoryStreamWriter,
+    create_writer_options,
+};
 use crate::progress::ElementsProgress;
 
 pub fn generate_blobs<R: Read>(
@@ -25,9 +30,13 @@ pub fn generate_blobs<R: Read>(
     progress.finish();
 }
 
+/// `header_sender` carries the decoded `HeaderBlock` provenance out to whatever
+/// builds the downstream writer, since that writer must exist (and have its
+/// schema metadata fixed) before the first `Elements` batch arrives.
 pub fn process_blobs(
     pbf_receiver: Receiver<Arc<BlobData>>,
     elements_sender: Sender<Elements>,
+    header_sender: Sender<OsmHeader>,
     progress: ElementsProgress,
 ) {
     for blob in pbf_receiver.iter() {
@@ -38,7 +47,11 @@ pub fn process_blobs(
                 progress.inc(elements.count());
                 elements_sender.send(elements).unwrap();
             }
-            _ => {}
+            OsmData::Header(header_block) => {
+                header_sender
+                    .send(OsmHeader::from_header_block(&header_block))
+                    .unwrap();
+            }
         }
     }
     progress.finish();
@@ -48,18 +61,29 @@ pub fn generate_parquet(
     elements_receiver: Receiver<Elements>,
     data_sender: Sender<ParquetData>,
     progress: ProgressBar,
+    node_config: ParquetFileConfig,
+    way_config: ParquetFileConfig,
+    relation_config: ParquetFileConfig,
+    header: Option<OsmHeader>,
 ) {
     let mut writer = OsmParquetStreamWriter::new(
         Box::new(ParquetMemoryStreamWriter::new(
             get_node_schema(),
-            None,
-            None,
+            Some(create_writer_options(&node_config)),
+            Some(node_config),
+            header.as_ref(),
+        )),
+        Box::new(ParquetMemoryStreamWriter::new(
+            get_way_schema(),
+            Some(create_writer_options(&way_config)),
+            Some(way_config),
+            header.as_ref(),
         )),
-        Box::new(ParquetMemoryStreamWriter::new(get_way_schema(), None, None)),
         Box::new(ParquetMemoryStreamWriter::new(
             get_relation_schema(),
-            None,
-            None,
+            Some(create_writer_options(&relation_config)),
+            Some(relation_config),
+            header.as_ref(),
         )),
     );
     for elements in elements_receiver.iter() {
@@ -84,19 +108,73 @@ pub fn write_files(
 ) {
     for data in data_receiver.iter() {
         match data {
-            ParquetData::Node(data) => {
+            ParquetData::Node(data, rows) => {
+                progress.inc(data.len() as u64);
+                writer.write_nodes(&data, rows);
+            }
+            ParquetData::Way(data, rows) => {
+                progress.inc(data.len() as u64);
+                writer.write_ways(&data, rows);
+            }
+            ParquetData::Relation(data, rows) => {
+                progress.inc(data.len() as u64);
+                writer.write_relations(&data, rows);
+            }
+        }
+    }
+    writer.finish();
+    progress.finish();
+}
+
+/// Arrow IPC counterpart to `generate_parquet`, selected in place of it when the
+/// configured output format is `.arrow`/Feather instead of Parquet.
+pub fn generate_arrow_ipc(
+    elements_receiver: Receiver<Elements>,
+    data_sender: Sender<ArrowIpcData>,
+    progress: ProgressBar,
+) {
+    let mut writer = OsmArrowIpcStreamWriter::new(
+        Box::new(ArrowIpcMemoryStreamWriter::new(get_node_schema(), None)),
+        Box::new(ArrowIpcMemoryStreamWriter::new(get_way_schema(), None)),
+        Box::new(ArrowIpcMemoryStreamWriter::new(get_relation_schema(), None)),
+    );
+    for elements in elements_receiver.iter() {
+        writer.write(&elements).unwrap();
+
+        for data in writer.flush(false).unwrap() {
+            progress.inc(1);
+            data_sender.send(data).unwrap();
+        }
+    }
+    for data in writer.flush(true).unwrap() {
+        progress.inc(1);
+        data_sender.send(data).unwrap();
+    }
+    progress.finish();
+}
+
+/// Arrow IPC counterpart to `write_files`.
+pub fn write_ipc_files(
+    data_receiver: Receiver<ArrowIpcData>,
+    writer: impl FileWriter,
+    progress: ProgressBar,
+) {
+    for data in data_receiver.iter() {
+        match data {
+            ArrowIpcData::Node(data, rows) => {
                 progress.inc(data.len() as u64);
-                writer.write_nodes(&data);
+                writer.write_nodes(&data, rows);
             }
-            ParquetData::Way(data) => {
+            ArrowIpcData::Way(data, rows) => {
                 progress.inc(data.len() as u64);
-                writer.write_ways(&data);
+                writer.write_ways(&data, rows);
             }
-            ParquetData::Relation(data) => {
+            ArrowIpcData::Relation(data, rows) => {
                 progress.inc(data.len() as u64);
-                writer.write_relations(&data);
+                writer.write_relations(&data, rows);
             }
         }
     }
+    writer.finish();
     progress.finish();
 }